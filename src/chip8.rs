@@ -0,0 +1,561 @@
+use std::{path::Path, fs::File, io::Read, time};
+use bit_iter::BitIter;
+use rand::random;
+
+pub const LORES_WIDTH: u16 = 64;
+pub const LORES_HEIGHT: u16 = 32;
+pub const HIRES_WIDTH: u16 = 128;
+pub const HIRES_HEIGHT: u16 = 64;
+pub const FONTSET_OFFSET: u8 = 0x50;
+
+// 60hz, independent of the CPU clock rate
+pub const TIMER_INTERVAL: time::Duration = time::Duration::from_nanos(1_000_000_000 / 60);
+
+const FONTSET: [u8; 80] =
+    [
+  0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+  0x20, 0x60, 0x20, 0x20, 0x70, // 1
+  0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+  0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+  0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+  0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+  0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+  0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+  0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+  0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+  0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+  0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+  0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+  0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+  0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+  0xF0, 0x80, 0xF0, 0x80, 0x80  // F
+];
+
+// receives the pixel buffer whenever draw_flag is set
+pub trait DisplaySink {
+    fn draw(&mut self, gfx: &[bool], width: u16, height: u16);
+}
+
+// reports which of the 16 CHIP-8 keys are currently held down
+pub trait InputSource {
+    fn key_down(&self, key: usize) -> bool;
+}
+
+// receives the XO-CHIP sample pattern/pitch and is gated by sound_timer
+pub trait AudioSink {
+    fn set_pattern(&mut self, pattern: [u8; 16]);
+    fn set_pitch(&mut self, pitch: u8);
+    fn set_playing(&mut self, playing: bool);
+}
+
+// ambiguous opcode behavior that differs between CHIP-8 interpreters; defaults
+// match the modern/CHIP-48 interpretation, the one this emulator used to hardcode
+#[derive(Clone, Copy)]
+pub struct Quirks {
+    pub shift_vy: bool, // 8XY6/8XYE shift VY into VX instead of shifting VX in place
+    pub load_store_increment: bool, // FX55/FX65 advance I by X+1 after the transfer
+    pub jump_vx: bool, // BNNN jumps to XNN + VX instead of NNN + V0
+    pub clip_sprites: bool // DXYN clips sprites at the screen edge instead of wrapping
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks {
+            shift_vy: false,
+            load_store_increment: false,
+            jump_vx: false,
+            clip_sprites: true
+        }
+    }
+}
+
+// tracks a fixed 60hz wall-clock cadence, independent of how often it is polled
+pub struct Timer {
+    last_tick: time::Instant
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer { last_tick: time::Instant::now() }
+    }
+
+    // true once per 60hz interval, regardless of how often this is called
+    pub fn due(&mut self) -> bool {
+        let now = time::Instant::now();
+        if now.duration_since(self.last_tick) >= TIMER_INTERVAL {
+            self.last_tick = now;
+            true
+        } else {
+            false
+        }
+    }
+
+    // decrements `value` once for every 60hz interval that has elapsed since the last tick
+    pub fn tick(&mut self, value: &mut u8) {
+        let now = time::Instant::now();
+        while now.duration_since(self.last_tick) >= TIMER_INTERVAL {
+            self.last_tick += TIMER_INTERVAL;
+            if *value > 0 { *value -= 1; }
+        }
+    }
+}
+
+pub struct Chip8 {
+    pub opcode: u16,
+    pub memory: [u8; 4096],
+    pub v: [u8; 16], // register
+    pub i: u16, // index
+    pub pc: u16, // program counter
+    pub gfx: Vec<bool>,
+    pub width: u16,
+    pub height: u16,
+    pub stack: Vec<u16>,
+    key: [bool; 16],
+    draw_flag: bool,
+    should_exit: bool,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub quirks: Quirks,
+    audio: Box<dyn AudioSink>
+}
+
+impl Chip8 {
+    pub fn new(quirks: Quirks, audio: Box<dyn AudioSink>) -> Chip8 {
+        Chip8 {
+            opcode: 0,
+            memory: [0; 4096],
+            v: [0; 16],
+            i: 0,
+            pc: 0x200,
+            gfx: vec![false; (LORES_WIDTH * LORES_HEIGHT) as usize],
+            width: LORES_WIDTH,
+            height: LORES_HEIGHT,
+            stack: Vec::new(),
+            key: [false; 16],
+            draw_flag: false,
+            should_exit: false,
+            delay_timer: 0,
+            sound_timer: 0,
+            quirks,
+            audio
+        }
+    }
+
+    pub fn init(&mut self) {
+        for (i, byte) in FONTSET.iter().enumerate() {
+            self.memory[i + FONTSET_OFFSET as usize] = *byte;
+        }
+    }
+
+    pub fn load_game(&mut self, file: &str) {
+        let path = Path::new(file);
+        let mut file = File::open(&path).expect("File not found!");
+        file.read(&mut self.memory[0x200..]).expect("Failed to read file!");
+    }
+
+    // populates key state from an input source; called once per frame by the driving loop
+    pub fn update_keys(&mut self, input: &dyn InputSource) {
+        for i in 0..self.key.len() {
+            self.key[i] = input.key_down(i);
+        }
+    }
+
+    // true once the ROM has executed a SUPER-CHIP 00FD exit opcode; the driving
+    // loop (or debugger) should stop calling run_cycle and shut down cleanly
+    pub fn should_exit(&self) -> bool {
+        self.should_exit
+    }
+
+    // hands the pixel buffer to the display sink if a draw happened since the last call
+    pub fn draw(&mut self, display: &mut dyn DisplaySink) {
+        if self.draw_flag {
+            display.draw(&self.gfx, self.width, self.height);
+            self.draw_flag = false;
+        }
+    }
+
+    // advances the delay/sound timers at their true 60hz rate and gates the audio sink
+    pub fn tick_timers(&mut self, delay_timer: &mut Timer, sound_timer: &mut Timer) {
+        delay_timer.tick(&mut self.delay_timer);
+        sound_timer.tick(&mut self.sound_timer);
+        self.audio.set_playing(self.sound_timer > 0);
+    }
+
+    // serializes the full machine state (memory, registers, stack, display,
+    // timers and keys) to a byte buffer that can be written to a .state file
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.memory);
+        bytes.extend_from_slice(&self.v);
+        bytes.extend_from_slice(&self.i.to_be_bytes());
+        bytes.extend_from_slice(&self.pc.to_be_bytes());
+        bytes.extend_from_slice(&(self.stack.len() as u16).to_be_bytes());
+        for frame in &self.stack {
+            bytes.extend_from_slice(&frame.to_be_bytes());
+        }
+        bytes.extend_from_slice(&self.width.to_be_bytes());
+        bytes.extend_from_slice(&self.height.to_be_bytes());
+        for pixel in &self.gfx {
+            bytes.push(*pixel as u8);
+        }
+        bytes.push(self.delay_timer);
+        bytes.push(self.sound_timer);
+        for key in &self.key {
+            bytes.push(*key as u8);
+        }
+        bytes
+    }
+
+    // restores a state previously produced by `snapshot`, leaving quirks untouched.
+    // fails instead of panicking if `bytes` is truncated, and then leaves the current
+    // state as it was; the stored width/height and any trailing bytes are not checked.
+    pub fn restore(&mut self, bytes: &[u8]) -> Result<(), &'static str> {
+        let backup = self.snapshot();
+        let result = self.read_state(bytes);
+        if result.is_err() {
+            self.read_state(&backup).expect("Failed to roll back save state!");
+        }
+        result
+    }
+
+    fn read_state(&mut self, bytes: &[u8]) -> Result<(), &'static str> {
+        let mut cursor = 0;
+        let mut take = |len: usize| -> Result<&[u8], &'static str> {
+            let end = cursor + len;
+            let slice = bytes.get(cursor..end).ok_or("Save state is truncated or corrupted.")?;
+            cursor = end;
+            Ok(slice)
+        };
+
+        self.memory.copy_from_slice(take(4096)?);
+        self.v.copy_from_slice(take(16)?);
+        self.i = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        self.pc = u16::from_be_bytes(take(2)?.try_into().unwrap());
+
+        let stack_len = u16::from_be_bytes(take(2)?.try_into().unwrap()) as usize;
+        self.stack.clear();
+        for _ in 0..stack_len {
+            self.stack.push(u16::from_be_bytes(take(2)?.try_into().unwrap()));
+        }
+
+        self.width = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        self.height = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        self.gfx = take((self.width as usize) * (self.height as usize))?.iter().map(|byte| *byte != 0).collect();
+
+        self.delay_timer = take(1)?[0];
+        self.sound_timer = take(1)?[0];
+        for key in self.key.iter_mut() {
+            *key = take(1)?[0] != 0;
+        }
+
+        self.draw_flag = true;
+        self.should_exit = false;
+        Ok(())
+    }
+
+    pub fn save_state(&self, path: &str) {
+        std::fs::write(path, self.snapshot()).expect("Failed to write save state!");
+    }
+
+    // loads a state previously written by `save_state`; fails harmlessly (e.g.
+    // no state has been saved yet, or the file is truncated/corrupted) instead
+    // of panicking, since this is triggered by an ordinary user keypress
+    pub fn load_state(&mut self, path: &str) -> Result<(), String> {
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        self.restore(&bytes).map_err(|e| e.to_string())
+    }
+
+    fn fetch_opcode(&mut self) {
+        let pc = self.pc as usize;
+        self.opcode = (self.memory[pc] as u16) << 8 | self.memory[pc + 1] as u16;
+        self.pc += 2;
+    }
+
+    // runs exactly one fetch/decode/execute cycle; used by the normal game loop
+    // and driven one step at a time by the debugger
+    pub fn run_cycle(&mut self) {
+        self.fetch_opcode();
+        self.emulate_cycle();
+    }
+
+    fn v_opcode(&self, opcode: u16, pattern: u16, shift: u8) -> u8 {
+        self.v[extract_usize(opcode, pattern, shift)]
+    }
+
+    // SUPER-CHIP: switches between the 64x32 and 128x64 display modes, clearing the screen
+    fn set_hires(&mut self, hires: bool) {
+        self.width = if hires { HIRES_WIDTH } else { LORES_WIDTH };
+        self.height = if hires { HIRES_HEIGHT } else { LORES_HEIGHT };
+        self.gfx = vec![false; (self.width as usize) * (self.height as usize)];
+        self.draw_flag = true;
+    }
+
+    fn scroll_down(&mut self, rows: usize) {
+        let width = self.width as usize;
+        let len = self.gfx.len();
+        self.gfx.copy_within(0..len - rows * width, rows * width);
+        self.gfx[0..rows * width].iter_mut().for_each(|pixel| *pixel = false);
+        self.draw_flag = true;
+    }
+
+    fn scroll_right(&mut self) {
+        let width = self.width as usize;
+        for row in self.gfx.chunks_mut(width) {
+            row.copy_within(0..width - 4, 4);
+            row[0..4].iter_mut().for_each(|pixel| *pixel = false);
+        }
+        self.draw_flag = true;
+    }
+
+    fn scroll_left(&mut self) {
+        let width = self.width as usize;
+        for row in self.gfx.chunks_mut(width) {
+            row.copy_within(4..width, 0);
+            row[width - 4..width].iter_mut().for_each(|pixel| *pixel = false);
+        }
+        self.draw_flag = true;
+    }
+
+    fn emulate_cycle(&mut self) {
+        let opcode = self.opcode;
+        match extract(opcode, 0xF000, 3) {
+            0 => match extract(opcode, 0x00FF, 0) {
+                0xE0 => self.gfx.iter_mut().for_each(|pixel| *pixel = false),
+                0xEE => self.pc = self.stack.pop().expect("Failed to pop from stack!"),
+                0xFB => self.scroll_right(), // SUPER-CHIP: scroll right 4px
+                0xFC => self.scroll_left(), // SUPER-CHIP: scroll left 4px
+                0xFD => self.should_exit = true, // SUPER-CHIP: exit interpreter
+                0xFE => self.set_hires(false), // SUPER-CHIP: lo-res mode
+                0xFF => self.set_hires(true), // SUPER-CHIP: hi-res mode
+                n if (0xC0..=0xCF).contains(&n) => self.scroll_down((n - 0xC0) as usize), // SUPER-CHIP: scroll down N rows
+                _ => invalid_opcode(opcode)
+            },
+            1 => self.pc = opcode & 0x0FFF,
+            2 => {
+                self.stack.push(self.pc);
+                self.pc = extract(opcode, 0x0FFF, 0);
+            }
+            3 => if self.v_opcode(opcode, 0x0F00, 2) == extract(opcode, 0x00FF, 0) as u8 { self.pc += 2; }
+            4 => if self.v_opcode(opcode, 0x0F00, 2) != extract(opcode, 0x00FF, 0) as u8 { self.pc += 2; },
+            5 => if self.v_opcode(opcode, 0x0F00, 2) == self.v_opcode(opcode, 0x00F0, 1) { self.pc += 2; },
+            6 => self.v[extract_usize(opcode, 0x0F00, 2)] = extract(opcode, 0x00FF, 0) as u8,
+            7 => self.v[extract_usize(opcode, 0x0F00, 2)] = (self.v_opcode(opcode, 0x0F00, 2) as u16 + extract(opcode, 0x00FF, 0)) as u8,
+            8 => match extract(opcode, 0x000F, 0) {
+                0 => self.v[extract_usize(opcode, 0x0F00, 2)] = self.v_opcode(opcode, 0x00F0, 1),
+                1 => self.v[extract_usize(opcode, 0x0F00, 2)] = self.v_opcode(opcode, 0x0F00, 2) | self.v_opcode(opcode, 0x00F0, 1),
+                2 => self.v[extract_usize(opcode, 0x0F00, 2)] = self.v_opcode(opcode, 0x0F00, 2) & self.v_opcode(opcode, 0x00F0, 1),
+                3 => self.v[extract_usize(opcode, 0x0F00, 2)] = self.v_opcode(opcode, 0x0F00, 2) ^ self.v_opcode(opcode, 0x00F0, 1),
+                4 => {
+                    let result = self.v_opcode(opcode, 0x0F00, 2) as u16 + self.v_opcode(opcode, 0x00F0, 1) as u16;
+                    self.v[0xF] = (result > 255) as u8;
+                    self.v[extract_usize(opcode, 0x0F00, 2)] = result as u8;
+                }
+                5 => {
+                    let (diff, carry) = subtract(self.v_opcode(opcode, 0x0F00, 2), self.v_opcode(opcode, 0x00F0, 1));
+                    self.v[0xF] = carry;
+                    self.v[extract_usize(opcode, 0x0F00, 2)] = diff;
+                }
+                6 => {
+                    let source = if self.quirks.shift_vy { self.v_opcode(opcode, 0x00F0, 1) } else { self.v_opcode(opcode, 0x0F00, 2) };
+                    self.v[0xF] = source & 1;
+                    self.v[extract_usize(opcode, 0x0F00, 2)] = source >> 1;
+                    }
+                0xE => {
+                    let source = if self.quirks.shift_vy { self.v_opcode(opcode, 0x00F0, 1) } else { self.v_opcode(opcode, 0x0F00, 2) };
+                    self.v[0xF] = (source >> 7) & 1;
+                    self.v[extract_usize(opcode, 0x0F00, 2)] = source << 1;
+                }
+                7 => {
+                    let (diff, carry) = subtract(self.v_opcode(opcode, 0x00F0, 1), self.v_opcode(opcode, 0x0F00, 2));
+                    self.v[0xF] = carry;
+                    self.v[extract_usize(opcode, 0x0F00, 2)] = diff;
+                }
+                _ => invalid_opcode(opcode)
+            }
+            9 => if self.v_opcode(opcode, 0x0F00, 2) != self.v_opcode(opcode, 0x00F0, 1) { self.pc += 2; }
+            0xA => self.i = extract(opcode, 0x0FFF, 0),
+            0xB => self.pc = if self.quirks.jump_vx {
+                extract(opcode, 0x0FFF, 0) + self.v_opcode(opcode, 0x0F00, 2) as u16
+            } else {
+                extract(opcode, 0x0FFF, 0) + self.v[0] as u16
+            },
+            0xC => self.v[extract_usize(opcode, 0x0F00, 2)] = random::<u8>() & extract(opcode, 0x00FF, 0) as u8,
+            0xE => match extract(opcode, 0x00FF, 0) {
+                0x9E => if self.key[self.v_opcode(opcode, 0x0F00, 2) as usize] { self.pc += 2; },
+                0xA1 => if !self.key[self.v_opcode(opcode, 0x0F00, 2) as usize] { self.pc += 2; },
+                _ => invalid_opcode(opcode)
+            }
+            0xD => {
+                let x0 = self.v[extract_usize(opcode, 0x0F00, 2)] % self.width as u8;
+                let y0 = self.v[extract_usize(opcode, 0x00F0, 1)];
+                let n = extract(opcode, 0x000F, 0);
+                // SUPER-CHIP: N == 0 draws a 16x16 sprite, two bytes per row, instead of an 8xN one
+                let (rows, row_bytes) = if n == 0 { (16, 2) } else { (n, 1) };
+                self.v[0xF] = 0;
+                self.draw_flag = true;
+
+                for row in 0..rows {
+                    let y = y0 + row as u8;
+
+                    for col_byte in 0..row_bytes {
+                        let byte = self.memory[(self.i + row * row_bytes + col_byte) as usize];
+                        let x_offset = (col_byte * 8) as u8;
+
+                        for bit in BitIter::from(byte.reverse_bits()) { // we need to iterate from right to left, so the lsb must be the msb
+                            let x = x0 + x_offset + bit as u8;
+
+                            let (x, y) = if self.quirks.clip_sprites {
+                                if x >= self.width as u8 || y >= self.height as u8 { break; };
+                                (x, y)
+                            } else {
+                                (x % self.width as u8, y % self.height as u8)
+                            };
+
+                            let sub_y = if y == 0 { 0 } else { y - 1};
+                            let screen_pixel = ((sub_y as u16) * self.width + x as u16) as usize;
+
+                            if self.gfx[screen_pixel] {
+                                self.v[0xF] = 1;
+                            }
+                            self.gfx[screen_pixel] = !self.gfx[screen_pixel];
+                        }
+                    }
+                }
+            }
+            0xF => match extract(opcode, 0x00FF, 0) {
+                7 => self.v[extract_usize(opcode, 0x0F00, 2)] = self.delay_timer,
+                0x15 => self.delay_timer = self.v_opcode(opcode, 0x0F00, 2),
+                0x18 => self.sound_timer = self.v_opcode(opcode, 0x0F00, 2),
+                0x02 => { // XO-CHIP: load the 16-byte sample pattern from memory[I..]
+                    let i = self.i as usize;
+                    let mut pattern = [0u8; 16];
+                    pattern.copy_from_slice(&self.memory[i..i + 16]);
+                    self.audio.set_pattern(pattern);
+                }
+                0x3A => self.audio.set_pitch(self.v_opcode(opcode, 0x0F00, 2)), // XO-CHIP: set playback pitch
+                0x1E => {
+                    self.i += self.v_opcode(opcode, 0x0F00, 2) as u16;
+                    self.v[0xF] = (self.i > 1000) as u8;
+                }
+                0x0A => {
+                    for (i, key) in self.key.iter().enumerate() {
+                        if *key {
+                            self.v[extract_usize(opcode, 0x0F00, 2)] = i as u8;
+                            return;
+                        }
+                    }
+                    self.pc -= 2;
+                }
+                0x29 => self.i = (self.v_opcode(opcode, 0x0F00, 2) & 0x0F) as u16 * 5 + FONTSET_OFFSET as u16,
+                0x33 => {
+                    let num = self.v_opcode(opcode, 0x0F00, 2).to_string();
+                    for (i, num) in num.bytes().enumerate() {
+                        self.memory[self.i as usize + i] = num - 48;
+                    }
+                }
+                0x55 => {
+                    let x = extract(opcode, 0x0F00, 2);
+                    for i in 0..=x {
+                        self.memory[(self.i + i) as usize] = self.v[i as usize];
+                    }
+                    if self.quirks.load_store_increment { self.i += x + 1; }
+                }
+                0x65 => {
+                    let x = extract(opcode, 0xF00, 2);
+                    for i in 0..=x {
+                        self.v[i as usize] = self.memory[(self.i + i) as usize];
+                    }
+                    if self.quirks.load_store_increment { self.i += x + 1; }
+                }
+                _ => invalid_opcode(opcode)
+            }
+            _ => invalid_opcode(opcode)
+            }
+    }
+}
+
+pub fn disassemble(opcode: u16) -> String {
+    let x = extract(opcode, 0x0F00, 2);
+    let y = extract(opcode, 0x00F0, 1);
+    let n = extract(opcode, 0x000F, 0);
+    let nn = extract(opcode, 0x00FF, 0);
+    let nnn = extract(opcode, 0x0FFF, 0);
+
+    match extract(opcode, 0xF000, 3) {
+        0 => match nn {
+            0xE0 => "CLS".to_string(),
+            0xEE => "RET".to_string(),
+            _ => format!("{:04x} (unknown)", opcode)
+        },
+        1 => format!("JP {:03x}", nnn),
+        2 => format!("CALL {:03x}", nnn),
+        3 => format!("SE V{:X}, {:02x}", x, nn),
+        4 => format!("SNE V{:X}, {:02x}", x, nn),
+        5 => format!("SE V{:X}, V{:X}", x, y),
+        6 => format!("LD V{:X}, {:02x}", x, nn),
+        7 => format!("ADD V{:X}, {:02x}", x, nn),
+        8 => format!("ALU V{:X}, V{:X} ({:X})", x, y, n),
+        9 => format!("SNE V{:X}, V{:X}", x, y),
+        0xA => format!("LD I, {:03x}", nnn),
+        0xB => format!("JP V0, {:03x}", nnn),
+        0xC => format!("RND V{:X}, {:02x}", x, nn),
+        0xD => format!("DRW V{:X}, V{:X}, {:X}", x, y, n),
+        0xE => format!("SKP/SKNP V{:X}", x),
+        0xF => format!("LD (F{:02x}) V{:X}", nn, x),
+        _ => format!("{:04x} (unknown)", opcode)
+    }
+}
+
+fn subtract(minuend: u8, subtrahend: u8) -> (u8, u8) {
+    let difference = minuend as i16 - subtrahend as i16;
+    let carry = minuend > subtrahend;
+    (difference as u8, carry as u8)
+}
+
+fn extract_usize(opcode: u16, pattern: u16, shift: u8) -> usize {
+    extract(opcode, pattern, shift) as usize
+}
+
+fn extract(opcode: u16, pattern: u16, shift: u8) -> u16 {
+    (opcode & pattern) >> shift * 4
+}
+
+fn invalid_opcode(opcode: u16) {
+    panic!("OpCode not found: {:x}", opcode);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NoopAudio;
+
+    impl AudioSink for NoopAudio {
+        fn set_pattern(&mut self, _pattern: [u8; 16]) {}
+        fn set_pitch(&mut self, _pitch: u8) {}
+        fn set_playing(&mut self, _playing: bool) {}
+    }
+
+    fn chip8_with(program: &[u8]) -> Chip8 {
+        let mut chip8 = Chip8::new(Quirks::default(), Box::new(NoopAudio));
+        chip8.memory[0x200..0x200 + program.len()].copy_from_slice(program);
+        chip8
+    }
+
+    // drives a cycle against a fake audio sink, without any window, and asserts on v/pc
+    #[test]
+    fn run_cycle_executes_one_opcode() {
+        let mut chip8 = chip8_with(&[0x60, 0x05]); // LD V0, 5
+        chip8.run_cycle();
+        assert_eq!(chip8.v[0], 5);
+        assert_eq!(chip8.pc, 0x202);
+    }
+
+    #[test]
+    fn superchip_exit_opcode_sets_should_exit_instead_of_killing_the_process() {
+        let mut chip8 = chip8_with(&[0x00, 0xFD]); // EXIT
+        assert!(!chip8.should_exit());
+        chip8.run_cycle();
+        assert!(chip8.should_exit());
+    }
+}
@@ -0,0 +1,349 @@
+use simple::{Window, Rect, Key};
+use std::{env, vec::Vec, thread::sleep, time, sync::{Arc, Mutex}};
+use rodio::{OutputStreamHandle, Sink, Source};
+use chip8::chip8::{Chip8, Quirks, Timer, AudioSink, DisplaySink, InputSource, disassemble, HIRES_WIDTH, HIRES_HEIGHT};
+
+const KEY_LAYOUT: [Key; 16] =
+[
+    Key::Num1, Key::Num2, Key::Num3, Key::Num4,
+    Key::Q, Key::W, Key::E, Key::R,
+    Key::A, Key::S, Key::D, Key::F,
+    Key::Z, Key::X, Key::C, Key::V
+];
+
+const FACTOR: u16 = 5; // pixel size at hi-res; lo-res pixels are drawn twice as large to fill the same window
+const DEFAULT_CYCLES_PER_SECOND: u32 = 500;
+
+pub struct SimpleDisplay<'a> {
+    window: &'a mut Window
+}
+
+impl<'a> SimpleDisplay<'a> {
+    pub fn new(window: &'a mut Window) -> SimpleDisplay<'a> {
+        SimpleDisplay { window }
+    }
+}
+
+impl<'a> DisplaySink for SimpleDisplay<'a> {
+    fn draw(&mut self, gfx: &[bool], width: u16, height: u16) {
+        self.window.clear();
+        let pixel_size = FACTOR * (HIRES_WIDTH / width);
+        for (i, pixel) in gfx.iter().enumerate() {
+            if *pixel {
+                let i = i as u16;
+                let y = i / width * pixel_size;
+                let x = i % width * pixel_size;
+                let rect = Rect::new(x as i32, y as i32, pixel_size as u32, pixel_size as u32);
+                self.window.fill_rect(rect);
+            }
+        }
+        let _ = height;
+    }
+}
+
+pub struct SimpleInput<'a> {
+    window: &'a Window
+}
+
+impl<'a> SimpleInput<'a> {
+    pub fn new(window: &'a Window) -> SimpleInput<'a> {
+        SimpleInput { window }
+    }
+}
+
+impl<'a> InputSource for SimpleInput<'a> {
+    fn key_down(&self, key: usize) -> bool {
+        self.window.is_key_down(KEY_LAYOUT[key])
+    }
+}
+
+// XO-CHIP sample playback state, shared with the audio thread's Source
+struct AudioState {
+    pattern: [u8; 16], // 128 one-bit PCM samples, MSB first
+    pitch: u8
+}
+
+impl Default for AudioState {
+    fn default() -> AudioState {
+        AudioState { pattern: [0; 16], pitch: 64 }
+    }
+}
+
+pub struct RodioAudio {
+    sink: Sink,
+    state: Arc<Mutex<AudioState>>
+}
+
+impl RodioAudio {
+    pub fn new(sink: Sink) -> RodioAudio {
+        let state = Arc::new(Mutex::new(AudioState::default()));
+        sink.append(XoChipSource::new(state.clone()));
+        RodioAudio { sink, state }
+    }
+}
+
+impl AudioSink for RodioAudio {
+    fn set_pattern(&mut self, pattern: [u8; 16]) {
+        self.state.lock().unwrap().pattern = pattern;
+    }
+
+    fn set_pitch(&mut self, pitch: u8) {
+        self.state.lock().unwrap().pitch = pitch;
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        if playing {
+            self.sink.play();
+        } else {
+            self.sink.pause();
+        }
+    }
+}
+
+// streams the XO-CHIP sample pattern as one-bit PCM, at a rate derived from the pitch register
+struct XoChipSource {
+    audio: Arc<Mutex<AudioState>>,
+    sample_index: usize
+}
+
+impl XoChipSource {
+    fn new(audio: Arc<Mutex<AudioState>>) -> XoChipSource {
+        XoChipSource { audio, sample_index: 0 }
+    }
+}
+
+impl Iterator for XoChipSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let audio = self.audio.lock().unwrap();
+        let bit = self.sample_index % 128;
+        let byte = audio.pattern[bit / 8];
+        let sample = (byte >> (7 - bit % 8)) & 1;
+        self.sample_index = self.sample_index.wrapping_add(1);
+        Some(if sample == 1 { i16::MAX } else { 0 })
+    }
+}
+
+impl Source for XoChipSource {
+    fn current_frame_len(&self) -> Option<usize> { None }
+    fn channels(&self) -> u16 { 1 }
+
+    fn sample_rate(&self) -> u32 {
+        let pitch = self.audio.lock().unwrap().pitch as f64;
+        (4000.0 * 2f64.powf((pitch - 64.0) / 48.0)) as u32
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> { None }
+}
+
+pub struct Debugger {
+    breakpoints: Vec<u16>,
+    last_command: Option<String>,
+    delay_timer: Timer,
+    sound_timer: Timer,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: Vec::new(),
+            last_command: None,
+            delay_timer: Timer::new(),
+            sound_timer: Timer::new(),
+        }
+    }
+
+    pub fn run(&mut self, chip8: &mut Chip8, window: &mut Window) {
+        println!("Entering debug mode. Type 'step', 'continue', 'break <addr>', 'regs', 'mem <addr> <len>' or 'dis'.");
+        loop {
+            if !window.next_frame() || chip8.should_exit() { return; }
+
+            println!("-> pc: {:04x}", chip8.pc);
+            let input = self.read_command();
+            let command = if input.trim().is_empty() {
+                match &self.last_command {
+                    Some(last) => last.clone(),
+                    None => continue
+                }
+            } else {
+                input.trim().to_string()
+            };
+            self.last_command = Some(command.clone());
+
+            let mut parts = command.split_whitespace();
+            match parts.next() {
+                Some("step") => {
+                    let count: u32 = parts.next().and_then(|n| n.parse().ok()).unwrap_or(1);
+                    for _ in 0..count {
+                        if !self.step(chip8, window) { break; }
+                        if self.breakpoints.contains(&chip8.pc) {
+                            println!("Hit breakpoint at {:04x}", chip8.pc);
+                            break;
+                        }
+                    }
+                }
+                Some("continue") => {
+                    loop {
+                        if !self.step(chip8, window) { break; }
+                        if self.breakpoints.contains(&chip8.pc) {
+                            println!("Hit breakpoint at {:04x}", chip8.pc);
+                            break;
+                        }
+                    }
+                }
+                Some("break") => {
+                    match parts.next().and_then(|addr| u16::from_str_radix(addr.trim_start_matches("0x"), 16).ok()) {
+                        Some(addr) => {
+                            self.breakpoints.push(addr);
+                            println!("Breakpoint set at {:04x}", addr);
+                        }
+                        None => println!("Usage: break <addr>")
+                    }
+                }
+                Some("regs") => self.print_regs(chip8),
+                Some("mem") => {
+                    let addr = parts.next().and_then(|addr| u16::from_str_radix(addr.trim_start_matches("0x"), 16).ok());
+                    let len = parts.next().and_then(|len| len.parse::<usize>().ok());
+                    match (addr, len) {
+                        (Some(addr), Some(len)) => self.print_mem(chip8, addr, len),
+                        _ => println!("Usage: mem <addr> <len>")
+                    }
+                }
+                Some("dis") => println!("{}", disassemble(self.peek_opcode(chip8))),
+                _ => println!("Unknown command: {}", command)
+            }
+        }
+    }
+
+    // advances the emulator by a single cycle, updating keys, timers and the
+    // display the same way the normal game loop does. returns false if the
+    // window was closed or the ROM requested an exit mid-step.
+    fn step(&mut self, chip8: &mut Chip8, window: &mut Window) -> bool {
+        chip8.update_keys(&SimpleInput::new(window));
+        chip8.run_cycle();
+        chip8.tick_timers(&mut self.delay_timer, &mut self.sound_timer);
+        chip8.draw(&mut SimpleDisplay::new(window));
+        window.next_frame() && !chip8.should_exit()
+    }
+
+    fn read_command(&self) -> String {
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).expect("Failed to read command!");
+        input
+    }
+
+    fn print_regs(&self, chip8: &Chip8) {
+        for (i, v) in chip8.v.iter().enumerate() {
+            println!("V{:X}: {:02x}", i, v);
+        }
+        println!("I:  {:04x}", chip8.i);
+        println!("PC: {:04x}", chip8.pc);
+        println!("SP: {:02x}", chip8.stack.len());
+    }
+
+    fn print_mem(&self, chip8: &Chip8, addr: u16, len: usize) {
+        if addr as usize >= chip8.memory.len() {
+            println!("Address {:04x} is out of range (memory is {:04x} bytes)", addr, chip8.memory.len());
+            return;
+        }
+        for (i, byte) in chip8.memory[addr as usize..].iter().take(len).enumerate() {
+            if i % 16 == 0 { print!("\n{:04x}: ", addr as usize + i); }
+            print!("{:02x} ", byte);
+        }
+        println!();
+    }
+
+    // decodes the opcode at the current (not yet executed) pc, without advancing it;
+    // chip8.opcode only reflects the *last executed* instruction
+    fn peek_opcode(&self, chip8: &Chip8) -> u16 {
+        let pc = chip8.pc as usize;
+        (chip8.memory[pc] as u16) << 8 | chip8.memory[pc + 1] as u16
+    }
+}
+
+// runs the normal (non-debug) game loop at a fixed cycles-per-second rate,
+// driving the emulator core through its Display/Input/Audio traits
+pub fn run_normal(mut chip8: Chip8, mut window: Window, cycles_per_second: u32, state_path: String) -> Result<(), &'static str> {
+    let cycle_delay = time::Duration::from_secs_f64(1.0 / cycles_per_second as f64);
+    let mut frame_timer = Timer::new();
+    let mut delay_timer = Timer::new();
+    let mut sound_timer = Timer::new();
+
+    let mut save_key_down = false;
+    let mut load_key_down = false;
+
+    loop {
+        let start = time::Instant::now();
+
+        if frame_timer.due() { // update display and timers at 60hz
+            if !window.next_frame() { return Ok(()); }
+            chip8.update_keys(&SimpleInput::new(&window));
+            chip8.tick_timers(&mut delay_timer, &mut sound_timer);
+
+            if window.is_key_down(Key::F5) {
+                if !save_key_down { chip8.save_state(&state_path); }
+                save_key_down = true;
+            } else {
+                save_key_down = false;
+            }
+
+            if window.is_key_down(Key::F9) {
+                if !load_key_down {
+                    if let Err(e) = chip8.load_state(&state_path) {
+                        println!("Could not load save state: {}", e);
+                    }
+                }
+                load_key_down = true;
+            } else {
+                load_key_down = false;
+            }
+        }
+
+        chip8.run_cycle();
+        chip8.draw(&mut SimpleDisplay::new(&mut window));
+        if chip8.should_exit() { return Ok(()); }
+
+        let took = time::Instant::now().duration_since(start);
+        sleep(cycle_delay.saturating_sub(took));
+    }
+}
+
+pub fn parse_args() -> Result<(String, Quirks, u32, bool), &'static str> {
+    let args: Vec<String> = env::args().collect();
+
+    let debug = args.iter().any(|arg| arg == "--debug" || arg == "-d");
+    let rom = args.iter().skip(1).find(|arg| !arg.starts_with('-'));
+
+    let rom = match rom {
+        Some(rom) => rom.clone(),
+        None => return Err("You must pass a chip8 programm to the emulator.")
+    };
+
+    let quirks = Quirks {
+        shift_vy: args.iter().any(|arg| arg == "--shift-vy"),
+        load_store_increment: args.iter().any(|arg| arg == "--load-store-increment"),
+        jump_vx: args.iter().any(|arg| arg == "--jump-vx"),
+        clip_sprites: !args.iter().any(|arg| arg == "--no-clip-sprites")
+    };
+
+    let cycles_per_second = match args.iter().position(|arg| arg == "--clock-rate")
+        .and_then(|i| args.get(i + 1)) {
+        Some(rate) => match rate.parse::<u32>() {
+            Ok(0) | Err(_) => return Err("--clock-rate must be a positive number of cycles per second."),
+            Ok(rate) => rate
+        },
+        None => DEFAULT_CYCLES_PER_SECOND
+    };
+
+    Ok((rom, quirks, cycles_per_second, debug))
+}
+
+pub fn new_window() -> Window {
+    Window::new("Chip 8 emulator", HIRES_WIDTH * FACTOR, HIRES_HEIGHT * FACTOR)
+}
+
+pub fn new_audio_sink(stream_handle: &OutputStreamHandle) -> RodioAudio {
+    let sink = Sink::try_new(stream_handle).unwrap();
+    RodioAudio::new(sink)
+}